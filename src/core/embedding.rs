@@ -0,0 +1,116 @@
+//! Module Embedding - Distance sémantique par embeddings
+//!
+//! Complète la NCD (qui approxime la distance sémantique via compression,
+//! bruitée sur les textes courts) par une distance cosinus calculée à
+//! partir de vecteurs d'embeddings.
+//!
+//! Auteur: Julien DABERT
+//! LDSI - Lyapunov-Dabert Stability Index
+
+use crate::core::ncd::{compute_ncd, NcdConfig};
+use crate::probe::injector::{Injector, InjectorError};
+
+/// Écart au-delà duquel NCD et cosinus sont considérés en désaccord
+const DISAGREEMENT_THRESHOLD: f64 = 0.4;
+
+/// Distance sémantique combinant NCD (compression) et similarité d'embeddings
+#[derive(Debug, Clone, Copy)]
+pub struct SemanticDistance {
+    /// Score NCD (0.0 = identique, ~1.0 = divergence maximale)
+    pub ncd: f64,
+    /// Distance cosinus entre les embeddings (0.0 = identique, 2.0 = opposés)
+    pub cosine: f64,
+}
+
+impl SemanticDistance {
+    /// `true` si NCD et cosinus divergent fortement, un signal à auditer
+    /// manuellement (ex: une paraphrase à sens identique donne un NCD élevé
+    /// mais un cosinus faible)
+    pub fn disagrees(&self) -> bool {
+        (self.ncd - self.cosine).abs() > DISAGREEMENT_THRESHOLD
+    }
+}
+
+/// Calcule la distance cosinus entre deux vecteurs: `1 - (a·b)/(‖a‖‖b‖)`
+///
+/// # Returns
+/// 0.0 si l'un des deux vecteurs est nul (évite une division par zéro)
+pub fn cosine_distance(a: &[f32], b: &[f32]) -> f64 {
+    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    1.0 - (dot / (norm_a * norm_b)) as f64
+}
+
+/// Calcule la `SemanticDistance` entre deux réponses: NCD (compression) et
+/// cosinus (embeddings récupérés via `injector`)
+///
+/// # Arguments
+/// * `injector` - Client LLM utilisé pour récupérer les embeddings
+/// * `text_a` - Réponse standard
+/// * `text_b` - Réponse fracturée/Codex
+/// * `ncd_config` - Backend de compression pour le volet NCD (voir [`NcdConfig`])
+pub async fn compute_semantic_distance(
+    injector: &Injector,
+    text_a: &str,
+    text_b: &str,
+    ncd_config: &NcdConfig,
+) -> Result<SemanticDistance, InjectorError> {
+    let ncd = compute_ncd(text_a, text_b, ncd_config).score;
+
+    let embedding_a = injector.embed(text_a).await?;
+    let embedding_b = injector.embed(text_b).await?;
+    let cosine = cosine_distance(&embedding_a, &embedding_b);
+
+    Ok(SemanticDistance { ncd, cosine })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine_distance_identical_vectors() {
+        let v = vec![1.0, 2.0, 3.0];
+        let distance = cosine_distance(&v, &v);
+        assert!(distance.abs() < 1e-6, "got {}", distance);
+    }
+
+    #[test]
+    fn test_cosine_distance_orthogonal_vectors() {
+        let a = vec![1.0, 0.0];
+        let b = vec![0.0, 1.0];
+        let distance = cosine_distance(&a, &b);
+        assert!((distance - 1.0).abs() < 1e-6, "got {}", distance);
+    }
+
+    #[test]
+    fn test_cosine_distance_zero_vector() {
+        let a = vec![0.0, 0.0];
+        let b = vec![1.0, 1.0];
+        assert_eq!(cosine_distance(&a, &b), 0.0);
+    }
+
+    #[test]
+    fn test_disagreement_flagged() {
+        let distance = SemanticDistance {
+            ncd: 0.9,
+            cosine: 0.1,
+        };
+        assert!(distance.disagrees());
+    }
+
+    #[test]
+    fn test_agreement_not_flagged() {
+        let distance = SemanticDistance {
+            ncd: 0.8,
+            cosine: 0.7,
+        };
+        assert!(!distance.disagrees());
+    }
+}
@@ -1,14 +1,14 @@
 //! Module NCD - Normalized Compression Distance
 //!
 //! Mesure la distance sémantique brute entre deux textes via compression.
-//! Basé sur la complexité de Kolmogorov approximée par Zstandard.
+//! Basé sur la complexité de Kolmogorov approximée par un compresseur
+//! configurable (Zstandard par défaut, avec dictionnaire entraîné optionnel).
 //!
 //! Auteur: Julien DABERT
 //! LDSI - Lyapunov-Dabert Stability Index
 
+use rayon::prelude::*;
 use std::cmp::{max, min};
-use std::io::Cursor;
-use zstd::stream::encode_all;
 
 /// Résultat détaillé du calcul NCD pour audit
 #[derive(Debug, Clone)]
@@ -31,18 +31,107 @@ pub struct NcdResult {
 /// Niveau 3 = bon compromis vitesse/ratio
 const COMPRESSION_LEVEL: i32 = 3;
 
-/// Calcule la taille compressée d'une chaîne via Zstandard
+/// Backend de compression utilisé pour approximer la complexité de
+/// Kolmogorov d'un texte. `Send + Sync` pour être partagé entre les threads
+/// rayon de `ncd_matrix`.
+pub trait Compressor: Send + Sync {
+    /// Compresse `input` et retourne sa taille en octets
+    fn compressed_size(&self, input: &[u8]) -> usize;
+}
+
+/// Compresseur Zstandard, avec dictionnaire entraîné optionnel
 ///
-/// # Arguments
-/// * `input` - Texte à compresser
+/// Un dictionnaire entraîné sur un corpus de réponses de référence (voir
+/// [`train_dictionary`]) modélise le vocabulaire partagé une seule fois, afin
+/// que la taille compressée de chaque texte reflète l'information réellement
+/// nouvelle plutôt que de refacturer ce vocabulaire commun - ce qui affine le
+/// signal de divergence 0.0-1.0 pour les réponses LLM courtes auditées par
+/// LDSI, là où le niveau 3 brut sous-approxime la complexité (overhead
+/// d'en-tête dominant sur les textes courts).
+pub struct ZstdCompressor {
+    level: i32,
+    dictionary: Option<Vec<u8>>,
+}
+
+impl ZstdCompressor {
+    /// Compresseur Zstandard sans dictionnaire, au niveau donné
+    pub fn new(level: i32) -> Self {
+        Self {
+            level,
+            dictionary: None,
+        }
+    }
+
+    /// Compresseur Zstandard utilisant un dictionnaire entraîné (voir
+    /// [`train_dictionary`]). Un dictionnaire vide (corpus d'entraînement
+    /// trop petit) est traité comme l'absence de dictionnaire.
+    pub fn with_dictionary(level: i32, dictionary: Vec<u8>) -> Self {
+        Self {
+            level,
+            dictionary: if dictionary.is_empty() { None } else { Some(dictionary) },
+        }
+    }
+}
+
+impl Default for ZstdCompressor {
+    fn default() -> Self {
+        Self::new(COMPRESSION_LEVEL)
+    }
+}
+
+impl Compressor for ZstdCompressor {
+    fn compressed_size(&self, input: &[u8]) -> usize {
+        let result = match &self.dictionary {
+            Some(dict) => zstd::bulk::Compressor::with_dictionary(self.level, dict)
+                .and_then(|mut c| c.compress(input)),
+            None => zstd::bulk::compress(input, self.level),
+        };
+        match result {
+            Ok(compressed) => compressed.len(),
+            Err(_) => input.len(), // Fallback: taille brute si erreur
+        }
+    }
+}
+
+/// Entraîne un dictionnaire Zstandard à partir d'un corpus de textes de
+/// référence (ex: un historique de réponses standards), pour [`ZstdCompressor::with_dictionary`]
 ///
-/// # Returns
-/// Taille en octets du texte compressé
-fn compressed_size(input: &str) -> usize {
-    let cursor = Cursor::new(input.as_bytes());
-    match encode_all(cursor, COMPRESSION_LEVEL) {
-        Ok(compressed) => compressed.len(),
-        Err(_) => input.len(), // Fallback: taille brute si erreur
+/// L'entraîneur zdict de zstd requiert un corpus substantiel (environ >100x
+/// la taille du dictionnaire visé, réparti sur de nombreux échantillons) pour
+/// converger. Un corpus trop petit ou trop court - le cas courant pour un
+/// historique de réponses LLM - le fait échouer avec une erreur `io`; dans ce
+/// cas, `train_dictionary` dégrade silencieusement vers l'absence de
+/// dictionnaire (`Vec` vide) plutôt que de propager l'erreur, puisque
+/// l'appelant ne peut de toute façon rien faire d'autre que fournir plus de
+/// données.
+///
+/// # Arguments
+/// * `corpus` - Échantillons de référence
+/// * `max_dict_size` - Taille maximale du dictionnaire entraîné, en octets
+pub fn train_dictionary(corpus: &[&str], max_dict_size: usize) -> Vec<u8> {
+    let samples: Vec<Vec<u8>> = corpus.iter().map(|text| text.as_bytes().to_vec()).collect();
+    zstd::dict::from_samples(&samples, max_dict_size).unwrap_or_default()
+}
+
+/// Configuration du calcul NCD: backend de compression utilisé
+pub struct NcdConfig {
+    /// Compresseur approximant la complexité de Kolmogorov
+    pub compressor: Box<dyn Compressor>,
+}
+
+impl Default for NcdConfig {
+    fn default() -> Self {
+        Self {
+            compressor: Box::new(ZstdCompressor::default()),
+        }
+    }
+}
+
+impl NcdConfig {
+    /// Configuration utilisant le compresseur donné (Zstandard avec
+    /// dictionnaire entraîné, ou toute autre implémentation de [`Compressor`])
+    pub fn new(compressor: Box<dyn Compressor>) -> Self {
+        Self { compressor }
     }
 }
 
@@ -53,6 +142,7 @@ fn compressed_size(input: &str) -> usize {
 /// # Arguments
 /// * `text_a` - Premier texte (réponse standard)
 /// * `text_b` - Second texte (réponse fracturée/Codex)
+/// * `config` - Backend de compression à utiliser (voir [`NcdConfig`])
 ///
 /// # Returns
 /// Structure NcdResult avec le score et les métriques d'audit
@@ -61,16 +151,38 @@ fn compressed_size(input: &str) -> usize {
 /// - NCD ≈ 0.0 : Textes quasi-identiques (lissage total)
 /// - NCD ≈ 0.5 : Divergence modérée
 /// - NCD ≈ 1.0 : Divergence maximale
-pub fn compute_ncd(text_a: &str, text_b: &str) -> NcdResult {
+pub fn compute_ncd(text_a: &str, text_b: &str, config: &NcdConfig) -> NcdResult {
     // Compression individuelle
-    let size_a = compressed_size(text_a);
-    let size_b = compressed_size(text_b);
+    let size_a = config.compressor.compressed_size(text_a.as_bytes());
+    let size_b = config.compressor.compressed_size(text_b.as_bytes());
 
     // Compression combinée (concaténation)
     let combined = format!("{}{}", text_a, text_b);
-    let size_combined = compressed_size(&combined);
+    let size_combined = config.compressor.compressed_size(combined.as_bytes());
+
+    let score = ncd_from_sizes(size_combined, size_a, size_b);
+
+    NcdResult {
+        score,
+        size_a,
+        size_b,
+        size_combined,
+        raw_size_a: text_a.len(),
+        raw_size_b: text_b.len(),
+    }
+}
 
-    // Calcul NCD
+/// Calcule uniquement le score NCD (version simplifiée), avec la
+/// configuration de compression par défaut (Zstandard niveau 3, sans
+/// dictionnaire)
+pub fn ncd_score(text_a: &str, text_b: &str) -> f64 {
+    compute_ncd(text_a, text_b, &NcdConfig::default()).score
+}
+
+/// Formule NCD à partir de tailles compressées déjà connues
+///
+/// Formule: NCD(x,y) = (C(xy) - min(C(x), C(y))) / max(C(x), C(y))
+fn ncd_from_sizes(size_combined: usize, size_a: usize, size_b: usize) -> f64 {
     let min_c = min(size_a, size_b) as f64;
     let max_c = max(size_a, size_b) as f64;
 
@@ -82,21 +194,70 @@ pub fn compute_ncd(text_a: &str, text_b: &str) -> NcdResult {
     };
 
     // Clamp [0.0, 1.5] - valeurs > 1.0 possibles avec certains compresseurs
-    let score = score.max(0.0).min(1.5);
+    score.max(0.0).min(1.5)
+}
 
-    NcdResult {
-        score,
-        size_a,
-        size_b,
-        size_combined,
-        raw_size_a: text_a.len(),
-        raw_size_b: text_b.len(),
-    }
+/// Résultat de `ncd_matrix`: matrice NCD symétrisée pour plusieurs textes
+#[derive(Debug, Clone)]
+pub struct NcdMatrixResult {
+    /// Matrice symétrisée: `matrix[i][j] = (NCD(i,j) + NCD(j,i)) / 2` pour
+    /// `i != j`; la diagonale n'est pas calculée et vaut 0.0
+    pub matrix: Vec<Vec<f64>>,
+    /// Tailles compressées individuelles `C(texts[i])`, réutilisables par
+    /// les appelants (clustering, dendrogramme) sans recompression
+    pub compressed_sizes: Vec<usize>,
 }
 
-/// Calcule uniquement le score NCD (version simplifiée)
-pub fn ncd_score(text_a: &str, text_b: &str) -> f64 {
-    compute_ncd(text_a, text_b).score
+/// Calcule la matrice NCD symétrisée pour un ensemble de textes
+///
+/// Compresse chaque texte une seule fois (`C(x)`, mis en cache) via le même
+/// [`Compressor`] que [`compute_ncd`] (voir [`NcdConfig`] - un dictionnaire
+/// entraîné y est particulièrement utile puisque le vocabulaire partagé
+/// entre les nombreuses paires comparées n'est modélisé qu'une fois), puis
+/// ne calcule que le triangle supérieur des combinaisons `C(xy)`, réparti
+/// sur un pool de threads rayon. NCD étant sensible à l'ordre de
+/// concaténation avec Zstandard, chaque paire est symétrisée:
+/// `ncd_sym(x,y) = (NCD(x,y) + NCD(y,x)) / 2`.
+///
+/// # Arguments
+/// * `texts` - Les textes à comparer deux à deux
+/// * `config` - Backend de compression à utiliser (voir [`NcdConfig`])
+///
+/// # Returns
+/// `NcdMatrixResult` avec la matrice symétrisée et les tailles compressées
+/// individuelles
+pub fn ncd_matrix(texts: &[&str], config: &NcdConfig) -> NcdMatrixResult {
+    let compressor = config.compressor.as_ref();
+    let n = texts.len();
+    let compressed_sizes: Vec<usize> = texts
+        .par_iter()
+        .map(|t| compressor.compressed_size(t.as_bytes()))
+        .collect();
+
+    // Triangle supérieur (i < j), compressé en parallèle par chunks
+    let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect();
+
+    let scores: Vec<(usize, usize, f64)> = pairs
+        .par_iter()
+        .map(|&(i, j)| {
+            let size_xy = compressor.compressed_size(format!("{}{}", texts[i], texts[j]).as_bytes());
+            let size_yx = compressor.compressed_size(format!("{}{}", texts[j], texts[i]).as_bytes());
+            let ncd_xy = ncd_from_sizes(size_xy, compressed_sizes[i], compressed_sizes[j]);
+            let ncd_yx = ncd_from_sizes(size_yx, compressed_sizes[j], compressed_sizes[i]);
+            (i, j, (ncd_xy + ncd_yx) / 2.0)
+        })
+        .collect();
+
+    let mut matrix = vec![vec![0.0; n]; n];
+    for (i, j, ncd_sym) in scores {
+        matrix[i][j] = ncd_sym;
+        matrix[j][i] = ncd_sym;
+    }
+
+    NcdMatrixResult {
+        matrix,
+        compressed_sizes,
+    }
 }
 
 #[cfg(test)]
@@ -106,7 +267,7 @@ mod tests {
     #[test]
     fn test_identical_texts() {
         let text = "Le chat dort sur le canapé.";
-        let result = compute_ncd(text, text);
+        let result = compute_ncd(text, text, &NcdConfig::default());
         // Textes identiques = NCD très faible
         assert!(result.score < 0.3, "NCD identique devrait être < 0.3, got {}", result.score);
     }
@@ -115,7 +276,7 @@ mod tests {
     fn test_different_texts() {
         let a = "Le chat dort paisiblement sur le canapé rouge.";
         let b = "La singularité quantique transcende les paradigmes ontologiques.";
-        let result = compute_ncd(a, b);
+        let result = compute_ncd(a, b, &NcdConfig::default());
         // Textes très différents = NCD élevé
         assert!(result.score > 0.5, "NCD différent devrait être > 0.5, got {}", result.score);
     }
@@ -124,7 +285,7 @@ mod tests {
     fn test_audit_trail() {
         let a = "Hello";
         let b = "World";
-        let result = compute_ncd(a, b);
+        let result = compute_ncd(a, b, &NcdConfig::default());
         // Vérification que les tailles sont cohérentes
         assert!(result.size_a > 0);
         assert!(result.size_b > 0);
@@ -132,4 +293,86 @@ mod tests {
         assert_eq!(result.raw_size_a, 5);
         assert_eq!(result.raw_size_b, 5);
     }
+
+    #[test]
+    fn test_train_dictionary_degrades_gracefully_on_small_corpus() {
+        // Corpus bien trop petit pour que zdict converge (il faut ~100x
+        // max_dict_size réparti sur de nombreux échantillons) - doit
+        // dégrader vers un dictionnaire vide plutôt que de paniquer.
+        let corpus = vec![
+            "Le chat dort sur le canapé rouge.",
+            "Le chien dort sur le tapis bleu.",
+            "Le chat mange sur la table verte.",
+        ];
+        let dict = train_dictionary(&corpus, 4096);
+        assert!(dict.is_empty(), "corpus trop petit: le dictionnaire devrait être vide");
+
+        // Un dictionnaire vide doit être traité comme l'absence de
+        // dictionnaire, sans paniquer ni fausser le calcul NCD.
+        let config = NcdConfig::new(Box::new(ZstdCompressor::with_dictionary(
+            COMPRESSION_LEVEL,
+            dict,
+        )));
+        let result = compute_ncd(
+            "Le chat dort sur le canapé rouge.",
+            "Le chat dort sur le canapé rouge.",
+            &config,
+        );
+        assert!(result.score < 0.3, "NCD identique devrait être < 0.3, got {}", result.score);
+    }
+
+    #[test]
+    fn test_ncd_matrix_shape_and_diagonal() {
+        let texts = vec!["Le chat dort.", "Le chien dort.", "La singularité transcende."];
+        let result = ncd_matrix(&texts, &NcdConfig::default());
+
+        assert_eq!(result.matrix.len(), texts.len());
+        assert_eq!(result.compressed_sizes.len(), texts.len());
+        for (i, row) in result.matrix.iter().enumerate() {
+            assert_eq!(row.len(), texts.len());
+            assert_eq!(row[i], 0.0, "diagonale non nulle à l'index {}", i);
+        }
+    }
+
+    #[test]
+    fn test_ncd_matrix_symmetric() {
+        let texts = vec!["Le chat dort.", "Le chien dort.", "La singularité transcende."];
+        let result = ncd_matrix(&texts, &NcdConfig::default());
+
+        for i in 0..texts.len() {
+            for j in 0..texts.len() {
+                assert_eq!(result.matrix[i][j], result.matrix[j][i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ncd_matrix_matches_pairwise_symmetrized_ncd() {
+        let texts = vec!["Bonjour le monde.", "Au revoir le monde."];
+        let result = ncd_matrix(&texts, &NcdConfig::default());
+
+        let expected = (ncd_score(texts[0], texts[1]) + ncd_score(texts[1], texts[0])) / 2.0;
+        assert!((result.matrix[0][1] - expected).abs() < 1e-9);
+    }
+
+    /// Compresseur factice (taille = longueur brute) pour vérifier que
+    /// `ncd_matrix` utilise bien le `Compressor` de la `NcdConfig` fournie
+    /// plutôt qu'un `ZstdCompressor::default()` codé en dur.
+    struct IdentityCompressor;
+
+    impl Compressor for IdentityCompressor {
+        fn compressed_size(&self, input: &[u8]) -> usize {
+            input.len()
+        }
+    }
+
+    #[test]
+    fn test_ncd_matrix_uses_configured_compressor() {
+        let texts = vec!["abc", "abcdef"];
+        let config = NcdConfig::new(Box::new(IdentityCompressor));
+        let result = ncd_matrix(&texts, &config);
+
+        // Avec le compresseur identité, C(x) = longueur brute de x
+        assert_eq!(result.compressed_sizes, vec![3, 6]);
+    }
 }
@@ -6,18 +6,24 @@
 //! Auteur: Julien DABERT
 //! LDSI - Lyapunov-Dabert Stability Index
 
+use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::time::Duration;
 
 /// Configuration de l'endpoint LLM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmConfig {
-    /// URL de base de l'API (ex: "http://localhost:11434" pour Ollama)
+    /// URL de base de l'API (ex: "http://localhost:11434" pour Ollama).
+    /// Laisser vide pour `ApiType::OpenAiCompatible` si `provider` doit
+    /// être résolu via la variable d'environnement `{PROVIDER}_API_BASE`.
     pub base_url: String,
     /// Modèle à utiliser (ex: "llama3", "gpt-4", "mistral")
     pub model: String,
-    /// Clé API (optionnel, pour OpenAI/Anthropic)
+    /// Clé API (optionnel, pour OpenAI/Anthropic/OpenAiCompatible).
+    /// Si absente pour `ApiType::OpenAiCompatible`, résolue via
+    /// `{PROVIDER}_API_KEY`.
     pub api_key: Option<String>,
     /// Timeout en secondes
     pub timeout_secs: u64,
@@ -27,6 +33,35 @@ pub struct LlmConfig {
     pub max_tokens: u32,
     /// Type d'API
     pub api_type: ApiType,
+    /// Nom court de l'hébergeur (ex: "groq", "mistral", "openrouter"),
+    /// utilisé comme préfixe des variables d'environnement de repli
+    /// (`{PROVIDER}_API_BASE`, `{PROVIDER}_API_KEY`) pour `OpenAiCompatible`.
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Chemin de l'endpoint de chat pour `ApiType::OpenAiCompatible`
+    /// (défaut: `/v1/chat/completions`)
+    #[serde(default)]
+    pub chat_endpoint: Option<String>,
+    /// Méthode d'authentification pour `ApiType::OpenAiCompatible`
+    #[serde(default)]
+    pub auth: AuthMethod,
+    /// Taille de la fenêtre de contexte Ollama (`num_ctx`), en tokens.
+    /// Ollama n'exposant pas d'API de comptage de tokens, ce champ évite de
+    /// tronquer silencieusement les prompts fracturés/Codex les plus longs.
+    /// Défaut: 4096 (valeur par défaut d'Ollama).
+    #[serde(default = "default_num_ctx")]
+    pub num_ctx: Option<u32>,
+    /// Si `true` (pertinent pour `ApiType::Ollama`), `inject_ab` appelle
+    /// `list_models()` avant d'injecter et échoue immédiatement avec un
+    /// `InjectorError::ApiError` nommant le modèle manquant plutôt que de
+    /// laisser la requête échouer en cours de route. Ignoré pour les autres
+    /// `ApiType`.
+    #[serde(default)]
+    pub verify_model: bool,
+}
+
+fn default_num_ctx() -> Option<u32> {
+    Some(4096)
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -37,6 +72,54 @@ pub enum ApiType {
     Ollama,
     /// Format Anthropic (/v1/messages)
     Anthropic,
+    /// Tout hébergeur parlant le format de chat OpenAI (Groq, Mistral,
+    /// OpenRouter, Together, DeepInfra, Fireworks, LocalAI, ...), avec
+    /// endpoint et authentification configurables
+    OpenAiCompatible,
+}
+
+/// Méthode d'authentification HTTP pour `ApiType::OpenAiCompatible`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum AuthMethod {
+    /// `Authorization: Bearer <clé>`
+    #[default]
+    Bearer,
+    /// `Authorization: Basic <base64(clé:)>` (RFC 7617 — la clé est
+    /// encodée en base64 avant l'envoi, jamais envoyée en clair)
+    Basic,
+    /// En-tête personnalisé: `<name>: <clé>`
+    Header { name: String },
+}
+
+/// Description d'un outil exposé au modèle (function calling)
+#[derive(Debug, Clone)]
+pub struct ToolSpec {
+    /// Nom de l'outil, tel qu'appelé par le modèle
+    pub name: String,
+    /// Description en langage naturel de ce que fait l'outil
+    pub description: String,
+    /// Schéma JSON des paramètres acceptés
+    pub parameters: Value,
+}
+
+/// Appel d'outil demandé par le modèle
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// Identifiant de l'appel, à renvoyer avec le résultat
+    pub id: String,
+    /// Nom de l'outil appelé
+    pub name: String,
+    /// Arguments fournis par le modèle
+    pub arguments: Value,
+}
+
+/// Résultat d'une injection avec outils disponibles
+#[derive(Debug, Clone, PartialEq)]
+pub enum InjectResult {
+    /// Réponse textuelle finale du modèle
+    Text(String),
+    /// Le modèle demande l'exécution d'un ou plusieurs outils
+    ToolCalls(Vec<ToolCall>),
 }
 
 impl Default for LlmConfig {
@@ -49,7 +132,43 @@ impl Default for LlmConfig {
             temperature: 0.7,
             max_tokens: 2048,
             api_type: ApiType::Ollama,
+            provider: None,
+            chat_endpoint: None,
+            auth: AuthMethod::Bearer,
+            num_ctx: default_num_ctx(),
+            verify_model: false,
+        }
+    }
+}
+
+impl LlmConfig {
+    /// Préfixe des variables d'environnement de repli, dérivé de `provider`
+    /// (ex: `provider: Some("groq")` -> `GROQ`)
+    fn env_prefix(&self) -> Option<String> {
+        self.provider.as_ref().map(|p| p.to_uppercase())
+    }
+
+    /// `base_url`, ou à défaut `{PROVIDER}_API_BASE` si `base_url` est vide
+    fn resolved_base_url(&self) -> Result<String, InjectorError> {
+        if !self.base_url.is_empty() {
+            return Ok(self.base_url.clone());
         }
+        self.env_prefix()
+            .and_then(|prefix| std::env::var(format!("{}_API_BASE", prefix)).ok())
+            .ok_or_else(|| {
+                InjectorError::ApiError(
+                    "base_url absent et aucune variable d'environnement *_API_BASE trouvée"
+                        .to_string(),
+                )
+            })
+    }
+
+    /// `api_key`, ou à défaut `{PROVIDER}_API_KEY` si absente
+    fn resolved_api_key(&self) -> Option<String> {
+        self.api_key.clone().or_else(|| {
+            self.env_prefix()
+                .and_then(|prefix| std::env::var(format!("{}_API_KEY", prefix)).ok())
+        })
     }
 }
 
@@ -61,6 +180,7 @@ struct OpenAiRequest {
     messages: Vec<OpenAiMessage>,
     temperature: f32,
     max_tokens: u32,
+    stream: bool,
 }
 
 #[derive(Serialize)]
@@ -84,6 +204,95 @@ struct OpenAiMessageResponse {
     content: String,
 }
 
+/// Chunk SSE `data: {...}` émis par OpenAI/compatible lorsque `stream: true`
+#[derive(Deserialize)]
+struct OpenAiStreamChunk {
+    choices: Vec<OpenAiStreamChoice>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiStreamChoice {
+    delta: OpenAiDelta,
+}
+
+#[derive(Deserialize, Default)]
+struct OpenAiDelta {
+    content: Option<String>,
+}
+
+/// Requête OpenAI/compatible avec appel d'outils (`tools`/`tool_choice`)
+#[derive(Serialize)]
+struct OpenAiRequestWithTools {
+    model: String,
+    messages: Vec<Value>,
+    temperature: f32,
+    max_tokens: u32,
+    tools: Vec<OpenAiToolDef>,
+    tool_choice: String,
+}
+
+#[derive(Serialize)]
+struct OpenAiToolDef {
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: OpenAiToolFunctionDef,
+}
+
+#[derive(Serialize)]
+struct OpenAiToolFunctionDef {
+    name: String,
+    description: String,
+    parameters: Value,
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponseWithTools {
+    choices: Vec<OpenAiChoiceWithTools>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiChoiceWithTools {
+    message: OpenAiMessageWithTools,
+}
+
+#[derive(Deserialize)]
+struct OpenAiMessageWithTools {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<OpenAiToolCallResponse>>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiToolCallResponse {
+    id: String,
+    function: OpenAiToolCallFunction,
+}
+
+#[derive(Deserialize)]
+struct OpenAiToolCallFunction {
+    name: String,
+    /// Les arguments sont transmis par l'API sous forme de chaîne JSON
+    arguments: String,
+}
+
+/// Requête OpenAI/compatible d'embedding (`/v1/embeddings`)
+#[derive(Serialize)]
+struct EmbeddingRequest {
+    model: String,
+    input: String,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingDatum>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingDatum {
+    embedding: Vec<f32>,
+}
+
 // ============ Structures de requête/réponse Ollama ============
 
 #[derive(Serialize)]
@@ -98,6 +307,8 @@ struct OllamaRequest {
 struct OllamaOptions {
     temperature: f32,
     num_predict: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -105,6 +316,37 @@ struct OllamaResponse {
     response: String,
 }
 
+/// Réponse de `GET /api/tags`, listant les modèles installés sur le serveur Ollama
+#[derive(Deserialize)]
+struct OllamaTagsResponse {
+    models: Vec<OllamaTagEntry>,
+}
+
+#[derive(Deserialize)]
+struct OllamaTagEntry {
+    name: String,
+}
+
+/// Requête Ollama d'embedding (`/api/embeddings`)
+#[derive(Serialize)]
+struct OllamaEmbeddingRequest {
+    model: String,
+    prompt: String,
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+/// Chunk newline-delimited émis par Ollama lorsque `stream: true`
+#[derive(Deserialize)]
+struct OllamaStreamChunk {
+    response: String,
+    #[serde(default)]
+    done: bool,
+}
+
 // ============ Structures Anthropic ============
 
 #[derive(Serialize)]
@@ -113,6 +355,7 @@ struct AnthropicRequest {
     messages: Vec<AnthropicMessage>,
     max_tokens: u32,
     temperature: f32,
+    stream: bool,
 }
 
 #[derive(Serialize)]
@@ -131,6 +374,62 @@ struct AnthropicContent {
     text: String,
 }
 
+/// Évènement SSE émis par Anthropic lorsque `stream: true`
+///
+/// Seul `content_block_delta` porte du texte ; `message_stop` termine le
+/// flux ; les autres types (`message_start`, `content_block_start`, ...)
+/// sont ignorés.
+#[derive(Deserialize)]
+struct AnthropicStreamEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(default)]
+    delta: Option<AnthropicStreamDelta>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicStreamDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+/// Requête Anthropic avec appel d'outils (`tools`)
+#[derive(Serialize)]
+struct AnthropicRequestWithTools {
+    model: String,
+    messages: Vec<Value>,
+    max_tokens: u32,
+    temperature: f32,
+    tools: Vec<AnthropicToolDef>,
+}
+
+#[derive(Serialize)]
+struct AnthropicToolDef {
+    name: String,
+    description: String,
+    input_schema: Value,
+}
+
+#[derive(Deserialize)]
+struct AnthropicResponseWithTools {
+    content: Vec<AnthropicContentBlockWithTools>,
+}
+
+/// Bloc de contenu Anthropic, texte ou `tool_use`
+#[derive(Deserialize)]
+struct AnthropicContentBlockWithTools {
+    #[serde(rename = "type")]
+    block_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    input: Option<Value>,
+}
+
 /// Erreur d'injection
 #[derive(Debug)]
 pub enum InjectorError {
@@ -153,6 +452,47 @@ impl std::fmt::Display for InjectorError {
 
 impl std::error::Error for InjectorError {}
 
+/// Convertit un `tool_call` OpenAI en `ToolCall`, en décodant la chaîne JSON
+/// `arguments`. Une chaîne mal formée est signalée en `ParseError` plutôt
+/// que remplacée silencieusement par `Value::Null`: un argument d'outil
+/// corrompu est exactement le type de divergence que LDSI doit relever.
+fn parse_openai_tool_call(call: OpenAiToolCallResponse) -> Result<ToolCall, InjectorError> {
+    let arguments = serde_json::from_str(&call.function.arguments).map_err(|e| {
+        InjectorError::ParseError(format!(
+            "arguments d'appel d'outil invalides pour `{}`: {} (reçu: {})",
+            call.function.name, e, call.function.arguments
+        ))
+    })?;
+    Ok(ToolCall {
+        id: call.id,
+        name: call.function.name,
+        arguments,
+    })
+}
+
+/// Extrait les lignes complètes accumulées dans `buf` (octets bruts d'un
+/// flux SSE/newline-delimited), en laissant les octets d'une ligne
+/// incomplète en fin de buffer pour le chunk suivant.
+///
+/// `\n` (0x0A) ne peut jamais apparaître au sein d'une séquence UTF-8
+/// multi-octets (les octets de continuation sont toujours >= 0x80) : couper
+/// sur ses positions dans le buffer brut est donc sûr, même si un chunk TCP
+/// a coupé un caractère accentué en deux. Décoder chunk par chunk avec
+/// `String::from_utf8_lossy` avant réassemblage produirait des `�` pour de
+/// tels caractères.
+fn drain_utf8_lines(buf: &mut Vec<u8>) -> Vec<String> {
+    let mut lines = Vec::new();
+    while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+        let line_bytes: Vec<u8> = buf.drain(..=pos).collect();
+        lines.push(
+            String::from_utf8_lossy(&line_bytes[..line_bytes.len() - 1])
+                .trim()
+                .to_string(),
+        );
+    }
+    lines
+}
+
 /// Client d'injection LLM
 pub struct Injector {
     client: Client,
@@ -182,6 +522,29 @@ impl Injector {
             ApiType::OpenAI => self.inject_openai(prompt).await,
             ApiType::Ollama => self.inject_ollama(prompt).await,
             ApiType::Anthropic => self.inject_anthropic(prompt).await,
+            ApiType::OpenAiCompatible => self.inject_openai_compatible(prompt).await,
+        }
+    }
+
+    /// Récupère le vecteur d'embedding d'un texte
+    ///
+    /// Supporte `ApiType::OpenAI`/`OpenAiCompatible` (`/v1/embeddings`) et
+    /// `ApiType::Ollama` (`/api/embeddings`). `ApiType::Anthropic` n'expose
+    /// pas d'API d'embeddings.
+    ///
+    /// # Arguments
+    /// * `text` - Texte à encoder
+    ///
+    /// # Returns
+    /// Le vecteur d'embedding
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>, InjectorError> {
+        match self.config.api_type {
+            ApiType::OpenAI => self.embed_openai(text).await,
+            ApiType::Ollama => self.embed_ollama(text).await,
+            ApiType::OpenAiCompatible => self.embed_openai_compatible(text).await,
+            ApiType::Anthropic => Err(InjectorError::ApiError(
+                "ApiType::Anthropic n'expose pas d'API d'embeddings".to_string(),
+            )),
         }
     }
 
@@ -196,6 +559,44 @@ impl Injector {
             }],
             temperature: self.config.temperature,
             max_tokens: self.config.max_tokens,
+            stream: false,
+        };
+
+        let mut req_builder = self.client.post(&url).json(&request);
+
+        if let Some(ref api_key) = self.config.api_key {
+            req_builder = req_builder.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = req_builder
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let parsed: OpenAiResponse = response
+            .json()
+            .await
+            .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+        parsed
+            .choices
+            .first()
+            .map(|c| c.message.content.clone())
+            .ok_or_else(|| InjectorError::ParseError("No response content".to_string()))
+    }
+
+    async fn embed_openai(&self, text: &str) -> Result<Vec<f32>, InjectorError> {
+        let url = format!("{}/v1/embeddings", self.config.base_url);
+
+        let request = EmbeddingRequest {
+            model: self.config.model.clone(),
+            input: text.to_string(),
         };
 
         let mut req_builder = self.client.post(&url).json(&request);
@@ -215,6 +616,66 @@ impl Injector {
             return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
         }
 
+        let parsed: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+        parsed
+            .data
+            .into_iter()
+            .next()
+            .map(|d| d.embedding)
+            .ok_or_else(|| InjectorError::ParseError("No embedding data".to_string()))
+    }
+
+    /// Variante d'`inject_openai` pour les hébergeurs compatibles
+    /// (Groq, Mistral, OpenRouter, Together, DeepInfra, Fireworks, LocalAI, ...),
+    /// avec `base_url`/`api_key` résolus depuis l'environnement et endpoint/auth
+    /// configurables via `LlmConfig`.
+    async fn inject_openai_compatible(&self, prompt: &str) -> Result<String, InjectorError> {
+        let base_url = self.config.resolved_base_url()?;
+        let endpoint = self
+            .config
+            .chat_endpoint
+            .clone()
+            .unwrap_or_else(|| "/v1/chat/completions".to_string());
+        let url = format!("{}{}", base_url, endpoint);
+
+        let request = OpenAiRequest {
+            model: self.config.model.clone(),
+            messages: vec![OpenAiMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+            stream: false,
+        };
+
+        let mut req_builder = self.client.post(&url).json(&request);
+
+        if let Some(api_key) = self.config.resolved_api_key() {
+            req_builder = match &self.config.auth {
+                AuthMethod::Bearer => {
+                    req_builder.header("Authorization", format!("Bearer {}", api_key))
+                }
+                AuthMethod::Basic => req_builder.basic_auth(api_key, None::<&str>),
+                AuthMethod::Header { name } => req_builder.header(name, api_key),
+            };
+        }
+
+        let response = req_builder
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
         let parsed: OpenAiResponse = response
             .json()
             .await
@@ -227,6 +688,51 @@ impl Injector {
             .ok_or_else(|| InjectorError::ParseError("No response content".to_string()))
     }
 
+    async fn embed_openai_compatible(&self, text: &str) -> Result<Vec<f32>, InjectorError> {
+        let base_url = self.config.resolved_base_url()?;
+        let url = format!("{}/v1/embeddings", base_url);
+
+        let request = EmbeddingRequest {
+            model: self.config.model.clone(),
+            input: text.to_string(),
+        };
+
+        let mut req_builder = self.client.post(&url).json(&request);
+
+        if let Some(api_key) = self.config.resolved_api_key() {
+            req_builder = match &self.config.auth {
+                AuthMethod::Bearer => {
+                    req_builder.header("Authorization", format!("Bearer {}", api_key))
+                }
+                AuthMethod::Basic => req_builder.basic_auth(api_key, None::<&str>),
+                AuthMethod::Header { name } => req_builder.header(name, api_key),
+            };
+        }
+
+        let response = req_builder
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let parsed: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+        parsed
+            .data
+            .into_iter()
+            .next()
+            .map(|d| d.embedding)
+            .ok_or_else(|| InjectorError::ParseError("No embedding data".to_string()))
+    }
+
     async fn inject_ollama(&self, prompt: &str) -> Result<String, InjectorError> {
         let url = format!("{}/api/generate", self.config.base_url);
 
@@ -237,6 +743,7 @@ impl Injector {
             options: OllamaOptions {
                 temperature: self.config.temperature,
                 num_predict: self.config.max_tokens,
+                num_ctx: self.config.num_ctx,
             },
         };
 
@@ -262,31 +769,17 @@ impl Injector {
         Ok(parsed.response)
     }
 
-    async fn inject_anthropic(&self, prompt: &str) -> Result<String, InjectorError> {
-        let url = format!("{}/v1/messages", self.config.base_url);
+    async fn embed_ollama(&self, text: &str) -> Result<Vec<f32>, InjectorError> {
+        let url = format!("{}/api/embeddings", self.config.base_url);
 
-        let request = AnthropicRequest {
+        let request = OllamaEmbeddingRequest {
             model: self.config.model.clone(),
-            messages: vec![AnthropicMessage {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-            }],
-            max_tokens: self.config.max_tokens,
-            temperature: self.config.temperature,
+            prompt: text.to_string(),
         };
 
-        let api_key = self
-            .config
-            .api_key
-            .as_ref()
-            .ok_or_else(|| InjectorError::ApiError("Anthropic requires API key".to_string()))?;
-
         let response = self
             .client
             .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
             .json(&request)
             .send()
             .await
@@ -298,47 +791,739 @@ impl Injector {
             return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
         }
 
-        let parsed: AnthropicResponse = response
+        let parsed: OllamaEmbeddingResponse = response
             .json()
             .await
             .map_err(|e| InjectorError::ParseError(e.to_string()))?;
 
-        parsed
-            .content
-            .first()
-            .map(|c| c.text.clone())
-            .ok_or_else(|| InjectorError::ParseError("No response content".to_string()))
+        Ok(parsed.embedding)
     }
 
-    /// Exécute une injection A/B (standard puis fracturé)
+    /// Liste les modèles installés sur le serveur Ollama (`GET /api/tags`)
     ///
-    /// # Arguments
-    /// * `prompt_standard` - Prompt de contrôle
-    /// * `prompt_fractured` - Prompt Codex/DAN
+    /// Sert aussi de vérification de santé/authentification: un appel
+    /// réussi confirme que le serveur est joignable avant de lancer une
+    /// injection A/B.
     ///
     /// # Returns
-    /// Tuple (réponse_standard, réponse_fracturée)
-    pub async fn inject_ab(
-        &self,
-        prompt_standard: &str,
-        prompt_fractured: &str,
-    ) -> Result<(String, String), InjectorError> {
-        // Exécution séquentielle pour garantir des sessions indépendantes
-        let response_a = self.inject(prompt_standard).await?;
-        let response_b = self.inject(prompt_fractured).await?;
-        Ok((response_a, response_b))
-    }
-}
+    /// Les noms des modèles installés (ex: `["llama3:latest", "mistral:latest"]`)
+    pub async fn list_models(&self) -> Result<Vec<String>, InjectorError> {
+        let url = format!("{}/api/tags", self.config.base_url);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let parsed: OllamaTagsResponse = response
+            .json()
+            .await
+            .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+        Ok(parsed.models.into_iter().map(|m| m.name).collect())
+    }
+
+    async fn inject_anthropic(&self, prompt: &str) -> Result<String, InjectorError> {
+        let url = format!("{}/v1/messages", self.config.base_url);
+
+        let request = AnthropicRequest {
+            model: self.config.model.clone(),
+            messages: vec![AnthropicMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+            max_tokens: self.config.max_tokens,
+            temperature: self.config.temperature,
+            stream: false,
+        };
+
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or_else(|| InjectorError::ApiError("Anthropic requires API key".to_string()))?;
+
+        let response = self
+            .client
+            .post(&url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let parsed: AnthropicResponse = response
+            .json()
+            .await
+            .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+        parsed
+            .content
+            .first()
+            .map(|c| c.text.clone())
+            .ok_or_else(|| InjectorError::ParseError("No response content".to_string()))
+    }
+
+    /// Envoie un prompt en mode streaming et transmet chaque fragment au fur et à mesure
+    ///
+    /// Utile pour les modèles locaux (Ollama) où les premiers tokens peuvent
+    /// tarder le temps que le modèle se charge en mémoire.
+    ///
+    /// # Arguments
+    /// * `prompt` - Le prompt à envoyer
+    /// * `handler` - Callback invoqué avec chaque fragment de texte reçu
+    ///
+    /// # Returns
+    /// La réponse complète assemblée, identique à ce que retournerait `inject`
+    pub async fn inject_stream(
+        &self,
+        prompt: &str,
+        mut handler: impl FnMut(&str),
+    ) -> Result<String, InjectorError> {
+        match self.config.api_type {
+            ApiType::OpenAI => self.inject_openai_stream(prompt, &mut handler).await,
+            ApiType::Ollama => self.inject_ollama_stream(prompt, &mut handler).await,
+            ApiType::Anthropic => self.inject_anthropic_stream(prompt, &mut handler).await,
+            ApiType::OpenAiCompatible => {
+                self.inject_openai_compatible_stream(prompt, &mut handler).await
+            }
+        }
+    }
+
+    async fn inject_openai_stream(
+        &self,
+        prompt: &str,
+        handler: &mut dyn FnMut(&str),
+    ) -> Result<String, InjectorError> {
+        let url = format!("{}/v1/chat/completions", self.config.base_url);
+        let mut req_builder = self.openai_stream_request(&url, prompt);
+
+        if let Some(ref api_key) = self.config.api_key {
+            req_builder = req_builder.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        self.run_openai_stream(req_builder, handler).await
+    }
+
+    /// Variante streaming d'`inject_openai_stream` pour `ApiType::OpenAiCompatible`
+    async fn inject_openai_compatible_stream(
+        &self,
+        prompt: &str,
+        handler: &mut dyn FnMut(&str),
+    ) -> Result<String, InjectorError> {
+        let base_url = self.config.resolved_base_url()?;
+        let endpoint = self
+            .config
+            .chat_endpoint
+            .clone()
+            .unwrap_or_else(|| "/v1/chat/completions".to_string());
+        let url = format!("{}{}", base_url, endpoint);
+        let mut req_builder = self.openai_stream_request(&url, prompt);
+
+        if let Some(api_key) = self.config.resolved_api_key() {
+            req_builder = match &self.config.auth {
+                AuthMethod::Bearer => {
+                    req_builder.header("Authorization", format!("Bearer {}", api_key))
+                }
+                AuthMethod::Basic => req_builder.basic_auth(api_key, None::<&str>),
+                AuthMethod::Header { name } => req_builder.header(name, api_key),
+            };
+        }
+
+        self.run_openai_stream(req_builder, handler).await
+    }
+
+    fn openai_stream_request(&self, url: &str, prompt: &str) -> reqwest::RequestBuilder {
+        let request = OpenAiRequest {
+            model: self.config.model.clone(),
+            messages: vec![OpenAiMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+            stream: true,
+        };
+
+        self.client.post(url).json(&request)
+    }
+
+    /// Envoie la requête SSE OpenAI/compatible et assemble la réponse en
+    /// transmettant chaque fragment de texte au `handler`
+    async fn run_openai_stream(
+        &self,
+        req_builder: reqwest::RequestBuilder,
+        handler: &mut dyn FnMut(&str),
+    ) -> Result<String, InjectorError> {
+        let response = req_builder
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let mut full = String::new();
+        let mut buf: Vec<u8> = Vec::new();
+        let mut stream = response.bytes_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+            buf.extend_from_slice(&chunk);
+
+            for line in drain_utf8_lines(&mut buf) {
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+                if data == "[DONE]" {
+                    return Ok(full);
+                }
+                if data.is_empty() {
+                    continue;
+                }
+
+                let parsed: OpenAiStreamChunk = serde_json::from_str(data)
+                    .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+                let content = parsed
+                    .choices
+                    .first()
+                    .and_then(|c| c.delta.content.clone())
+                    .unwrap_or_default();
+                if !content.is_empty() {
+                    handler(&content);
+                    full.push_str(&content);
+                }
+            }
+        }
+
+        Ok(full)
+    }
+
+    async fn inject_ollama_stream(
+        &self,
+        prompt: &str,
+        handler: &mut dyn FnMut(&str),
+    ) -> Result<String, InjectorError> {
+        let url = format!("{}/api/generate", self.config.base_url);
+
+        let request = OllamaRequest {
+            model: self.config.model.clone(),
+            prompt: prompt.to_string(),
+            stream: true,
+            options: OllamaOptions {
+                temperature: self.config.temperature,
+                num_predict: self.config.max_tokens,
+                num_ctx: self.config.num_ctx,
+            },
+        };
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let mut full = String::new();
+        let mut buf: Vec<u8> = Vec::new();
+        let mut stream = response.bytes_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+            buf.extend_from_slice(&chunk);
+
+            for line in drain_utf8_lines(&mut buf) {
+                if line.is_empty() {
+                    continue;
+                }
+
+                let parsed: OllamaStreamChunk = serde_json::from_str(&line)
+                    .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+                if !parsed.response.is_empty() {
+                    handler(&parsed.response);
+                    full.push_str(&parsed.response);
+                }
+                if parsed.done {
+                    return Ok(full);
+                }
+            }
+        }
+
+        Ok(full)
+    }
+
+    async fn inject_anthropic_stream(
+        &self,
+        prompt: &str,
+        handler: &mut dyn FnMut(&str),
+    ) -> Result<String, InjectorError> {
+        let url = format!("{}/v1/messages", self.config.base_url);
+
+        let request = AnthropicRequest {
+            model: self.config.model.clone(),
+            messages: vec![AnthropicMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+            max_tokens: self.config.max_tokens,
+            temperature: self.config.temperature,
+            stream: true,
+        };
+
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or_else(|| InjectorError::ApiError("Anthropic requires API key".to_string()))?;
+
+        let response = self
+            .client
+            .post(&url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let mut full = String::new();
+        let mut buf: Vec<u8> = Vec::new();
+        let mut stream = response.bytes_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+            buf.extend_from_slice(&chunk);
+
+            for line in drain_utf8_lines(&mut buf) {
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+                if data.is_empty() {
+                    continue;
+                }
+
+                let parsed: AnthropicStreamEvent = serde_json::from_str(data)
+                    .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+                if parsed.event_type == "message_stop" {
+                    return Ok(full);
+                }
+                if parsed.event_type == "content_block_delta" {
+                    let text = parsed.delta.and_then(|d| d.text).unwrap_or_default();
+                    if !text.is_empty() {
+                        handler(&text);
+                        full.push_str(&text);
+                    }
+                }
+            }
+        }
+
+        Ok(full)
+    }
+
+    /// Envoie un prompt avec des outils disponibles et retourne soit la
+    /// réponse texte finale, soit les appels d'outils demandés par le modèle
+    ///
+    /// # Arguments
+    /// * `prompt` - Le prompt à envoyer
+    /// * `tools` - Les outils que le modèle peut choisir d'appeler
+    pub async fn inject_with_tool_specs(
+        &self,
+        prompt: &str,
+        tools: &[ToolSpec],
+    ) -> Result<InjectResult, InjectorError> {
+        let messages = vec![json!({"role": "user", "content": prompt})];
+        self.run_tool_turn(&messages, tools).await
+    }
+
+    /// Boucle d'appel d'outils multi-étapes
+    ///
+    /// Envoie `prompt`, et tant que le modèle répond par des appels d'outils,
+    /// exécute chacun via `executor` et renvoie le résultat au modèle, jusqu'à
+    /// obtenir une réponse textuelle finale ou atteindre `max_iterations`.
+    ///
+    /// # Arguments
+    /// * `prompt` - Le prompt initial
+    /// * `tools` - Les outils disponibles pour le modèle
+    /// * `executor` - Exécute un appel d'outil et renvoie son résultat en JSON
+    /// * `max_iterations` - Nombre maximal d'allers-retours modèle/outils
+    pub async fn inject_with_tools(
+        &self,
+        prompt: &str,
+        tools: &[ToolSpec],
+        mut executor: impl FnMut(&ToolCall) -> Value,
+        max_iterations: usize,
+    ) -> Result<String, InjectorError> {
+        let mut messages = vec![json!({"role": "user", "content": prompt})];
+
+        for _ in 0..max_iterations {
+            match self.run_tool_turn(&messages, tools).await? {
+                InjectResult::Text(text) => return Ok(text),
+                InjectResult::ToolCalls(calls) => {
+                    let results: Vec<Value> = calls.iter().map(&mut executor).collect();
+                    messages.extend(self.build_tool_followup_messages(&calls, &results));
+                }
+            }
+        }
+
+        Err(InjectorError::ApiError(format!(
+            "inject_with_tools: max_iterations ({}) atteint sans réponse finale",
+            max_iterations
+        )))
+    }
+
+    async fn run_tool_turn(
+        &self,
+        messages: &[Value],
+        tools: &[ToolSpec],
+    ) -> Result<InjectResult, InjectorError> {
+        match self.config.api_type {
+            ApiType::OpenAI => {
+                let url = format!("{}/v1/chat/completions", self.config.base_url);
+                let mut req = self.openai_tool_request(&url, messages, tools);
+                if let Some(ref api_key) = self.config.api_key {
+                    req = req.header("Authorization", format!("Bearer {}", api_key));
+                }
+                self.run_openai_tool_request(req).await
+            }
+            ApiType::OpenAiCompatible => {
+                let base_url = self.config.resolved_base_url()?;
+                let endpoint = self
+                    .config
+                    .chat_endpoint
+                    .clone()
+                    .unwrap_or_else(|| "/v1/chat/completions".to_string());
+                let url = format!("{}{}", base_url, endpoint);
+                let mut req = self.openai_tool_request(&url, messages, tools);
+                if let Some(api_key) = self.config.resolved_api_key() {
+                    req = match &self.config.auth {
+                        AuthMethod::Bearer => {
+                            req.header("Authorization", format!("Bearer {}", api_key))
+                        }
+                        AuthMethod::Basic => req.basic_auth(api_key, None::<&str>),
+                        AuthMethod::Header { name } => req.header(name, api_key),
+                    };
+                }
+                self.run_openai_tool_request(req).await
+            }
+            ApiType::Anthropic => self.anthropic_tool_turn(messages, tools).await,
+            ApiType::Ollama => Err(InjectorError::ApiError(
+                "Ollama (/api/generate) ne supporte pas l'appel d'outils".to_string(),
+            )),
+        }
+    }
+
+    fn openai_tool_request(
+        &self,
+        url: &str,
+        messages: &[Value],
+        tools: &[ToolSpec],
+    ) -> reqwest::RequestBuilder {
+        let request = OpenAiRequestWithTools {
+            model: self.config.model.clone(),
+            messages: messages.to_vec(),
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+            tools: tools
+                .iter()
+                .map(|t| OpenAiToolDef {
+                    tool_type: "function".to_string(),
+                    function: OpenAiToolFunctionDef {
+                        name: t.name.clone(),
+                        description: t.description.clone(),
+                        parameters: t.parameters.clone(),
+                    },
+                })
+                .collect(),
+            tool_choice: "auto".to_string(),
+        };
+
+        self.client.post(url).json(&request)
+    }
+
+    async fn run_openai_tool_request(
+        &self,
+        req_builder: reqwest::RequestBuilder,
+    ) -> Result<InjectResult, InjectorError> {
+        let response = req_builder
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let parsed: OpenAiResponseWithTools = response
+            .json()
+            .await
+            .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+        let message = parsed
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message)
+            .ok_or_else(|| InjectorError::ParseError("No response content".to_string()))?;
+
+        match message.tool_calls {
+            Some(calls) if !calls.is_empty() => {
+                let calls = calls
+                    .into_iter()
+                    .map(parse_openai_tool_call)
+                    .collect::<Result<Vec<_>, InjectorError>>()?;
+                Ok(InjectResult::ToolCalls(calls))
+            }
+            _ => Ok(InjectResult::Text(message.content.unwrap_or_default())),
+        }
+    }
+
+    async fn anthropic_tool_turn(
+        &self,
+        messages: &[Value],
+        tools: &[ToolSpec],
+    ) -> Result<InjectResult, InjectorError> {
+        let url = format!("{}/v1/messages", self.config.base_url);
+
+        let request = AnthropicRequestWithTools {
+            model: self.config.model.clone(),
+            messages: messages.to_vec(),
+            max_tokens: self.config.max_tokens,
+            temperature: self.config.temperature,
+            tools: tools
+                .iter()
+                .map(|t| AnthropicToolDef {
+                    name: t.name.clone(),
+                    description: t.description.clone(),
+                    input_schema: t.parameters.clone(),
+                })
+                .collect(),
+        };
+
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or_else(|| InjectorError::ApiError("Anthropic requires API key".to_string()))?;
+
+        let response = self
+            .client
+            .post(&url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| InjectorError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(InjectorError::ApiError(format!("{}: {}", status, body)));
+        }
+
+        let parsed: AnthropicResponseWithTools = response
+            .json()
+            .await
+            .map_err(|e| InjectorError::ParseError(e.to_string()))?;
+
+        let mut calls = Vec::new();
+        let mut text = String::new();
+
+        for block in parsed.content {
+            match block.block_type.as_str() {
+                "tool_use" => calls.push(ToolCall {
+                    id: block.id.unwrap_or_default(),
+                    name: block.name.unwrap_or_default(),
+                    arguments: block.input.unwrap_or(Value::Null),
+                }),
+                "text" => {
+                    if let Some(t) = block.text {
+                        text.push_str(&t);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if calls.is_empty() {
+            Ok(InjectResult::Text(text))
+        } else {
+            Ok(InjectResult::ToolCalls(calls))
+        }
+    }
+
+    /// Construit les messages de suivi (assistant + résultats d'outils) dans
+    /// le format attendu par le backend courant, pour `inject_with_tools`
+    fn build_tool_followup_messages(&self, calls: &[ToolCall], results: &[Value]) -> Vec<Value> {
+        match self.config.api_type {
+            ApiType::Anthropic => {
+                let assistant_content: Vec<Value> = calls
+                    .iter()
+                    .map(|c| {
+                        json!({
+                            "type": "tool_use",
+                            "id": c.id,
+                            "name": c.name,
+                            "input": c.arguments,
+                        })
+                    })
+                    .collect();
+
+                let tool_results: Vec<Value> = calls
+                    .iter()
+                    .zip(results.iter())
+                    .map(|(c, r)| {
+                        json!({
+                            "type": "tool_result",
+                            "tool_use_id": c.id,
+                            "content": r.to_string(),
+                        })
+                    })
+                    .collect();
+
+                vec![
+                    json!({"role": "assistant", "content": assistant_content}),
+                    json!({"role": "user", "content": tool_results}),
+                ]
+            }
+            ApiType::OpenAI | ApiType::OpenAiCompatible | ApiType::Ollama => {
+                let mut out = vec![json!({
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": calls.iter().map(|c| json!({
+                        "id": c.id,
+                        "type": "function",
+                        "function": { "name": c.name, "arguments": c.arguments.to_string() },
+                    })).collect::<Vec<_>>(),
+                })];
+
+                out.extend(calls.iter().zip(results.iter()).map(|(c, r)| {
+                    json!({
+                        "role": "tool",
+                        "tool_call_id": c.id,
+                        "content": r.to_string(),
+                    })
+                }));
+
+                out
+            }
+        }
+    }
+
+    /// Exécute une injection A/B (standard puis fracturé)
+    ///
+    /// Si `config.verify_model` est activé (pertinent pour `ApiType::Ollama`),
+    /// appelle d'abord `list_models()` et échoue immédiatement avec un
+    /// `InjectorError::ApiError` nommant le modèle manquant plutôt que de
+    /// laisser la requête échouer en cours de route.
+    ///
+    /// # Arguments
+    /// * `prompt_standard` - Prompt de contrôle
+    /// * `prompt_fractured` - Prompt Codex/DAN
+    ///
+    /// # Returns
+    /// Tuple (réponse_standard, réponse_fracturée)
+    pub async fn inject_ab(
+        &self,
+        prompt_standard: &str,
+        prompt_fractured: &str,
+    ) -> Result<(String, String), InjectorError> {
+        if self.config.verify_model && self.config.api_type == ApiType::Ollama {
+            let installed = self.list_models().await?;
+            let available = installed.iter().any(|name| {
+                name == &self.config.model || name.split(':').next() == Some(self.config.model.as_str())
+            });
+            if !available {
+                return Err(InjectorError::ApiError(format!(
+                    "modèle '{}' introuvable sur le serveur Ollama (modèles installés: {})",
+                    self.config.model,
+                    installed.join(", ")
+                )));
+            }
+        }
+
+        // Exécution séquentielle pour garantir des sessions indépendantes
+        let response_a = self.inject(prompt_standard).await?;
+        let response_b = self.inject(prompt_fractured).await?;
+        Ok((response_a, response_b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
     fn test_default_config() {
         let config = LlmConfig::default();
         assert_eq!(config.api_type, ApiType::Ollama);
         assert!(config.base_url.contains("11434"));
+        assert_eq!(config.num_ctx, Some(4096));
+    }
+
+    #[test]
+    fn test_parse_ollama_tags_response() {
+        let parsed: OllamaTagsResponse = serde_json::from_str(
+            r#"{"models": [{"name": "llama3:latest"}, {"name": "mistral:latest"}]}"#,
+        )
+        .unwrap();
+        let names: Vec<&str> = parsed.models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["llama3:latest", "mistral:latest"]);
+    }
+
+    #[test]
+    fn test_parse_openai_embedding_response() {
+        let parsed: EmbeddingResponse =
+            serde_json::from_str(r#"{"data": [{"embedding": [0.1, 0.2, 0.3]}]}"#).unwrap();
+        assert_eq!(parsed.data[0].embedding, vec![0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    fn test_parse_ollama_embedding_response() {
+        let parsed: OllamaEmbeddingResponse =
+            serde_json::from_str(r#"{"embedding": [0.4, 0.5]}"#).unwrap();
+        assert_eq!(parsed.embedding, vec![0.4, 0.5]);
     }
 
     #[test]
@@ -347,4 +1532,203 @@ mod tests {
         let _injector = Injector::new(config);
         // Test que la création ne panique pas
     }
+
+    #[test]
+    fn test_resolved_base_url_from_explicit_field() {
+        let config = LlmConfig {
+            base_url: "https://api.groq.com".to_string(),
+            provider: Some("groq".to_string()),
+            ..LlmConfig::default()
+        };
+        assert_eq!(config.resolved_base_url().unwrap(), "https://api.groq.com");
+    }
+
+    #[test]
+    fn test_resolved_base_url_from_env_fallback() {
+        let config = LlmConfig {
+            base_url: String::new(),
+            provider: Some("test_provider_chunk0_2".to_string()),
+            ..LlmConfig::default()
+        };
+        // SAFETY: test-only, no other thread touches this process's env in this test binary.
+        unsafe {
+            std::env::set_var("TEST_PROVIDER_CHUNK0_2_API_BASE", "https://example.test");
+        }
+        assert_eq!(
+            config.resolved_base_url().unwrap(),
+            "https://example.test"
+        );
+        unsafe {
+            std::env::remove_var("TEST_PROVIDER_CHUNK0_2_API_BASE");
+        }
+    }
+
+    #[test]
+    fn test_resolved_base_url_missing_fails() {
+        let config = LlmConfig {
+            base_url: String::new(),
+            provider: None,
+            ..LlmConfig::default()
+        };
+        assert!(config.resolved_base_url().is_err());
+    }
+
+    #[test]
+    fn test_parse_openai_tool_call_response() {
+        let parsed: OpenAiResponseWithTools = serde_json::from_str(
+            r#"{"choices": [{"message": {"content": null, "tool_calls": [
+                {"id": "call_1", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}}
+            ]}}]}"#,
+        )
+        .unwrap();
+        let tool_calls = parsed.choices[0].message.tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls[0].id, "call_1");
+        assert_eq!(tool_calls[0].function.name, "get_weather");
+    }
+
+    #[test]
+    fn test_parse_openai_tool_call_malformed_arguments_is_parse_error() {
+        let call = OpenAiToolCallResponse {
+            id: "call_1".to_string(),
+            function: OpenAiToolCallFunction {
+                name: "get_weather".to_string(),
+                arguments: "{not valid json".to_string(),
+            },
+        };
+
+        let err = parse_openai_tool_call(call).unwrap_err();
+        match err {
+            InjectorError::ParseError(msg) => {
+                assert!(msg.contains("get_weather"));
+                assert!(msg.contains("{not valid json"));
+            }
+            other => panic!("expected ParseError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_anthropic_tool_use_block() {
+        let parsed: AnthropicResponseWithTools = serde_json::from_str(
+            r#"{"content": [{"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.content[0].block_type, "tool_use");
+        assert_eq!(parsed.content[0].id.as_deref(), Some("toolu_1"));
+    }
+
+    #[test]
+    fn test_build_tool_followup_messages_openai() {
+        let injector = Injector::new(LlmConfig {
+            api_type: ApiType::OpenAI,
+            ..LlmConfig::default()
+        });
+        let calls = vec![ToolCall {
+            id: "call_1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: serde_json::json!({"city": "Paris"}),
+        }];
+        let results = vec![serde_json::json!({"temp_c": 18})];
+        let messages = injector.build_tool_followup_messages(&calls, &results);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "assistant");
+        assert_eq!(messages[1]["role"], "tool");
+        assert_eq!(messages[1]["tool_call_id"], "call_1");
+    }
+
+    #[test]
+    fn test_build_tool_followup_messages_anthropic() {
+        let injector = Injector::new(LlmConfig {
+            api_type: ApiType::Anthropic,
+            ..LlmConfig::default()
+        });
+        let calls = vec![ToolCall {
+            id: "toolu_1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: serde_json::json!({"city": "Paris"}),
+        }];
+        let results = vec![serde_json::json!({"temp_c": 18})];
+        let messages = injector.build_tool_followup_messages(&calls, &results);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "assistant");
+        assert_eq!(messages[1]["role"], "user");
+        assert_eq!(messages[1]["content"][0]["tool_use_id"], "toolu_1");
+    }
+
+    #[test]
+    fn test_drain_utf8_lines_reassembles_multibyte_char_split_across_chunks() {
+        // "café" - le 'é' (2 octets UTF-8) est coupé entre deux chunks TCP
+        let full_line = "café\n".as_bytes().to_vec();
+        let (first, second) = full_line.split_at(4); // coupe au milieu des 2 octets de 'é'
+
+        let mut buf: Vec<u8> = Vec::new();
+        buf.extend_from_slice(first);
+        assert!(drain_utf8_lines(&mut buf).is_empty(), "pas de '\\n' encore reçu");
+
+        buf.extend_from_slice(second);
+        let lines = drain_utf8_lines(&mut buf);
+        assert_eq!(lines, vec!["café".to_string()]);
+    }
+
+    #[test]
+    fn test_drain_utf8_lines_keeps_incomplete_trailing_line() {
+        let mut buf: Vec<u8> = b"line1\nline2".to_vec();
+        let lines = drain_utf8_lines(&mut buf);
+        assert_eq!(lines, vec!["line1".to_string()]);
+        assert_eq!(buf, b"line2");
+    }
+
+    #[test]
+    fn test_parse_ollama_stream_chunk() {
+        let chunk: OllamaStreamChunk =
+            serde_json::from_str(r#"{"response": "Bonjour", "done": false}"#).unwrap();
+        assert_eq!(chunk.response, "Bonjour");
+        assert!(!chunk.done);
+
+        let done_chunk: OllamaStreamChunk =
+            serde_json::from_str(r#"{"response": "", "done": true}"#).unwrap();
+        assert!(done_chunk.done);
+    }
+
+    #[test]
+    fn test_parse_openai_stream_chunk() {
+        let chunk: OpenAiStreamChunk =
+            serde_json::from_str(r#"{"choices": [{"delta": {"content": "Bon"}}]}"#).unwrap();
+        assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("Bon"));
+    }
+
+    #[test]
+    fn test_parse_anthropic_stream_event() {
+        let event: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type": "content_block_delta", "delta": {"text": "Bon"}}"#,
+        )
+        .unwrap();
+        assert_eq!(event.event_type, "content_block_delta");
+        assert_eq!(event.delta.unwrap().text.as_deref(), Some("Bon"));
+
+        let stop_event: AnthropicStreamEvent =
+            serde_json::from_str(r#"{"type": "message_stop"}"#).unwrap();
+        assert_eq!(stop_event.event_type, "message_stop");
+    }
+
+    #[test]
+    fn test_auth_method_basic_encodes_key_as_base64() {
+        // `reqwest::RequestBuilder::basic_auth` encode `user:password` en
+        // base64 (RFC 7617); ici la clé API tient lieu d'utilisateur et
+        // aucun mot de passe n'est fourni.
+        let request = Client::new()
+            .get("http://example.com")
+            .basic_auth("secret-key", None::<&str>)
+            .build()
+            .unwrap();
+
+        let header = request
+            .headers()
+            .get("Authorization")
+            .expect("Authorization header manquant")
+            .to_str()
+            .unwrap();
+
+        // base64("secret-key:")
+        assert_eq!(header, "Basic c2VjcmV0LWtleTo=");
+    }
 }